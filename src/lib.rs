@@ -1,8 +1,108 @@
-use url::Url;
+use url::{form_urlencoded, Host, Url};
 use std::io::prelude::*;
 
+/// The class of a Spartan response status code, a single digit per the protocol.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StatusClass {
+    /// `2`: request succeeded; `meta` is the MIME type of the body.
+    Success,
+    /// `3`: redirect; `meta` is the target URL.
+    Redirect,
+    /// `4`: client error; `meta` is a human-readable error message.
+    ClientError,
+    /// `5`: server error; `meta` is a human-readable error message.
+    ServerError,
+}
+
+impl StatusClass {
+    fn from_code(code: u32) -> Result<StatusClass, &'static str> {
+	match code {
+	    2 => Ok(StatusClass::Success),
+	    3 => Ok(StatusClass::Redirect),
+	    4 => Ok(StatusClass::ClientError),
+	    5 => Ok(StatusClass::ServerError),
+	    _ => Err("Unknown status class"),
+	}
+    }
+}
+
+/// A parsed Spartan server reply: a status line (`<code> <meta>\r\n`) followed by a body.
+///
+/// The body is kept as raw bytes since Spartan servers routinely return non-UTF-8 content
+/// (images, compressed data, arbitrary files); use [`Response::body_text`] to decode it when
+/// `meta` says it's text.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Response {
+    code: u32,
+    class: StatusClass,
+    meta: String,
+    body: Vec<u8>,
+}
+
+impl Response {
+    /// Parses a raw server reply into a `Response`, splitting off the status line.
+    ///
+    /// The status line is always ASCII, so it's located by scanning for `\r\n` in the byte
+    /// stream; everything after it is kept as an opaque body.
+    pub fn parse(raw: &[u8]) -> Result<Response, &'static str> {
+	let crlf = raw.windows(2).position(|w| w == b"\r\n");
+	let (status_line, rest) = match crlf {
+	    None => return Err("Malformed status line"),
+	    Some(i) => (&raw[..i], &raw[i + 2..]),
+	};
+
+	let status_line = match std::str::from_utf8(status_line) {
+	    Err(_) => return Err("Malformed status line"),
+	    Ok(s) => s,
+	};
+
+	let (code_str, meta) = match status_line.find(' ') {
+	    None => return Err("Malformed status line"),
+	    Some(i) => (&status_line[..i], &status_line[i + 1..]),
+	};
+
+	let code: u32 = match code_str.parse() {
+	    Err(_) => return Err("Malformed status line"),
+	    Ok(c) => c,
+	};
+
+	let class = StatusClass::from_code(code)?;
+
+	Ok(Response { code, class, meta: meta.to_string(), body: rest.to_vec() })
+    }
+
+    /// The single-digit status code sent by the server.
+    pub fn code(&self) -> u32 {
+	self.code
+    }
+
+    /// The status class (2/3/4/5) derived from `code`.
+    pub fn class(&self) -> StatusClass {
+	self.class
+    }
+
+    /// The meta string: a MIME type on success, a redirect target on `3`, or an error message.
+    pub fn meta(&self) -> &str {
+	&self.meta
+    }
+
+    /// The raw response body, i.e. everything after the status line.
+    pub fn body(&self) -> &[u8] {
+	&self.body
+    }
+
+    /// Decodes the body as UTF-8 text, if `meta` indicates a text MIME type.
+    pub fn body_text(&self) -> Result<String, &'static str> {
+	if !self.meta.starts_with("text/") {
+	    return Err("Meta type is not text");
+	}
+
+	String::from_utf8(self.body.clone()).map_err(|_| "Body is not valid UTF-8")
+    }
+}
+
 pub struct Request {
-    host: String,
+    host: Host<String>,
     path: String,
     content_length: usize,
     data: Option<String>
@@ -14,25 +114,54 @@ impl Request {
 	    return Err("Not a spartan URL")
 	}
 
-	match parsed_url.host_str() {
+	match parsed_url.host() {
 	    None => Err("No hostname"),
 	    Some(unparsed_host) => {
 		let (data, content_length) = match parsed_url.query() {
 		    None => (None, 0),
 		    Some(s) => {
 			let decoded_data = urlencoding::decode(s).unwrap().into_owned();
-			let decoded_len = decoded_data.chars().count();
+			let decoded_len = decoded_data.len();
 			(Some(decoded_data), decoded_len)
 		    }
 		};
 
-		let host = unparsed_host.to_string();
+		let host = unparsed_host.to_owned();
 		let path = parsed_url.path().to_string();
-		
+
 		Ok(Request { host, path, data, content_length })
 	    }
 	}
     }
+
+    /// Builds a request whose data block is a `application/x-www-form-urlencoded` encoding of
+    /// `pairs`, for submitting form-style input without smuggling it through the URL query string.
+    pub fn with_form<I, K, V>(host: impl AsRef<str>, path: impl Into<String>, pairs: I) -> Result<Request, &'static str>
+    where
+	I: IntoIterator<Item = (K, V)>,
+	K: AsRef<str>,
+	V: AsRef<str>,
+    {
+	let host = match Host::parse(host.as_ref()) {
+	    Err(_) => return Err("Invalid host"),
+	    Ok(host) => host,
+	};
+	let data = form_urlencoded::Serializer::new(String::new())
+	    .extend_pairs(pairs)
+	    .finish();
+	let content_length = data.len();
+
+	Ok(Request { host, path: path.into(), content_length, data: Some(data) })
+    }
+
+    /// The request's destination host: a domain name, an IPv4 literal, or an IPv6 literal.
+    ///
+    /// Exposed as the typed [`Host`] (rather than a raw string) so callers can branch on host
+    /// kind before connecting — e.g. to reject IP literals, or to key a TOFU/known-hosts policy
+    /// on whether they're dealing with a domain or an IP address.
+    pub fn host(&self) -> &Host<String> {
+	&self.host
+    }
 }
 
 
@@ -45,22 +174,22 @@ impl std::fmt::Display for Request {
     }
 }
 
-pub fn get(r: String) -> Result<String, &'static str> {
+pub fn get(r: String) -> Result<Response, &'static str> {
     match Url::parse(&r) {
 	Err(_) => Err("Cannot parse url"),
 	Ok(url) => {
 	    let port = url.port_or_known_default().unwrap(); // patched version of `uri` DOES have a default
 	    let request = Request::from_url(url)?;
-	    let mut connection = std::net::TcpStream::connect(format!("{}:{}", request.host, port)).unwrap();
+	    let mut connection = std::net::TcpStream::connect(format!("{}:{}", request.host(), port)).unwrap();
 	    let request_string = request.to_string();
-	    
+
 	    match connection.write_all(request_string.as_bytes()) {
 		Err(_) => Err("Error writing to socket"),
 		Ok(()) => {
-		    let mut buffer = String::new();
-		    match connection.read_to_string(&mut buffer) {
+		    let mut buffer = Vec::new();
+		    match connection.read_to_end(&mut buffer) {
 			Err(_) => Err("Unable to read response"),
-			Ok(_) => Ok(buffer)
+			Ok(_) => Response::parse(&buffer)
 		    }
 		}
 	    }
@@ -70,7 +199,8 @@ pub fn get(r: String) -> Result<String, &'static str> {
 
 #[cfg(test)]
 mod tests {
-    use crate::Request;
+    use crate::{Request, Response, StatusClass};
+    use url::Host;
 
     /// taken from 5.1 of the spec
     
@@ -145,5 +275,94 @@ mod tests {
 	let request = Request::from_url(url::Url::parse("spartan://example.com?hello%20world").unwrap()).unwrap();
 	assert_eq!("example.com / 11\r\nhello world", format!("{}", request))
     }
-    
+
+    #[test]
+    fn response_parsing_success() {
+	let response = Response::parse(b"2 text/gemini\r\n# Hello\r\n").unwrap();
+	assert_eq!(2, response.code());
+	assert_eq!(StatusClass::Success, response.class());
+	assert_eq!("text/gemini", response.meta());
+	assert_eq!(b"# Hello\r\n", response.body());
+	assert_eq!("# Hello\r\n", response.body_text().unwrap());
+    }
+
+    #[test]
+    fn response_parsing_redirect() {
+	let response = Response::parse(b"3 spartan://example.com/new\r\n").unwrap();
+	assert_eq!(StatusClass::Redirect, response.class());
+	assert_eq!("spartan://example.com/new", response.meta());
+    }
+
+    #[test]
+    fn response_parsing_client_error() {
+	let response = Response::parse(b"4 Not found\r\n").unwrap();
+	assert_eq!(StatusClass::ClientError, response.class());
+    }
+
+    #[test]
+    fn response_parsing_server_error() {
+	let response = Response::parse(b"5 Internal error\r\n").unwrap();
+	assert_eq!(StatusClass::ServerError, response.class());
+    }
+
+    #[test]
+    fn response_parsing_malformed_status_line() {
+	assert!(Response::parse(b"not a status line").is_err());
+	assert!(Response::parse(b"2text/gemini\r\n").is_err());
+	assert!(Response::parse(b"xx meta\r\nbody").is_err());
+    }
+
+    #[test]
+    fn response_parsing_binary_body() {
+	let response = Response::parse(b"2 image/png\r\n\x89PNG\x00\x01").unwrap();
+	assert_eq!(b"\x89PNG\x00\x01", response.body());
+	assert!(response.body_text().is_err());
+    }
+
+    #[test]
+    fn request_content_length_counts_bytes_not_chars() {
+	let request = Request::from_url(url::Url::parse("spartan://example.com?caf%C3%A9").unwrap()).unwrap();
+	assert_eq!("example.com / 5\r\ncafé", format!("{}", request))
+    }
+
+    #[test]
+    fn request_with_form() {
+	let request = Request::with_form("example.com", "/", [("a", "1"), ("b", "2")]).unwrap();
+	assert_eq!("example.com / 7\r\na=1&b=2", format!("{}", request))
+    }
+
+    #[test]
+    fn request_with_form_escapes_special_characters() {
+	let request = Request::with_form("example.com", "/submit", [("hello", "a b&c")]).unwrap();
+	assert_eq!("example.com /submit 13\r\nhello=a+b%26c", format!("{}", request))
+    }
+
+    #[test]
+    fn request_with_form_rejects_invalid_host() {
+	assert!(Request::with_form("exa mple.com", "/", [("a", "1")]).is_err());
+    }
+
+    #[test]
+    fn host_accessor_reports_domain() {
+	let request = Request::from_url(url::Url::parse("spartan://example.com/").unwrap()).unwrap();
+	assert_eq!(&Host::Domain("example.com".to_string()), request.host());
+    }
+
+    #[test]
+    fn host_accessor_reports_ipv4() {
+	let request = Request::from_url(url::Url::parse("spartan://127.0.0.1/").unwrap()).unwrap();
+	assert_eq!(&Host::<String>::Ipv4(std::net::Ipv4Addr::new(127, 0, 0, 1)), request.host());
+    }
+
+    #[test]
+    fn host_accessor_reports_ipv6() {
+	let request = Request::from_url(url::Url::parse("spartan://[::1]/").unwrap()).unwrap();
+	assert_eq!(&Host::<String>::Ipv6(std::net::Ipv6Addr::LOCALHOST), request.host());
+    }
+
+    #[test]
+    fn host_accessor_normalizes_punycode() {
+	let request = Request::from_url(url::Url::parse("spartan://examplé.com/").unwrap()).unwrap();
+	assert_eq!(&Host::Domain("xn--exampl-gva.com".to_string()), request.host());
+    }
 }